@@ -33,6 +33,7 @@ use mononoke_types::{
     BlobstoreValue, BonsaiChangeset, ChangesetId, Generation, MononokeId, RepositoryId, Timestamp,
 };
 use phases::{HeadsFetcher, Phases, SqlPhasesFactory};
+use redactedblobstore::{CensoredBlob, RedactedBlobs};
 use repo_blobstore::{RepoBlobstore, RepoBlobstoreArgs};
 use stats::prelude::*;
 use std::{
@@ -77,6 +78,22 @@ define_stats! {
     create_changeset_cf_count: timeseries("create_changeset.changed_files_count"; Average, Sum),
 }
 
+/// Build the `changeset_fetcher_factory` closure for a given `Changesets` and `RepositoryId`.
+/// Every place that installs a new `Changesets` implementation on a `BlobRepo` (construction,
+/// `DangerousOverride`, `BlobRepoBuilder`) must rebuild the factory through this function so it
+/// can never drift out of sync with the `changesets` it's derived from.
+fn build_changeset_fetcher_factory(
+    changesets: &Arc<dyn Changesets>,
+    repoid: RepositoryId,
+) -> Arc<dyn Fn() -> Arc<dyn ChangesetFetcher + Send + Sync> + Send + Sync> {
+    cloned!(changesets, repoid);
+    Arc::new(move || {
+        let res: Arc<dyn ChangesetFetcher + Send + Sync> =
+            Arc::new(SimpleChangesetFetcher::new(changesets.clone(), repoid.clone()));
+        res
+    })
+}
+
 pub struct BlobRepo {
     blobstore: RepoBlobstore,
     bookmarks: Arc<dyn Bookmarks>,
@@ -94,6 +111,10 @@ pub struct BlobRepo {
     derived_data_config: DerivedDataConfig,
     reponame: String,
     attributes: Arc<TypeMap>,
+    // Contains the set of blobstore keys that have been redacted (censored). This is the
+    // same `Arc` that the `CensoredBlob` layer wrapping `blobstore` consults, so the set
+    // returned by `redacted_blobs()` is the one blobstore reads are checked against.
+    redacted_blobs: Arc<RedactedBlobs>,
 }
 
 impl BlobRepo {
@@ -113,19 +134,26 @@ impl BlobRepo {
         derived_data_config: DerivedDataConfig,
         reponame: String,
         attributes: Arc<TypeMap>,
+        redacted_blobs: Arc<RedactedBlobs>,
     ) -> Self {
         let (blobstore, repoid) = blobstore_args.into_blobrepo_parts();
 
-        let changeset_fetcher_factory = {
-            cloned!(changesets, repoid);
-            move || {
-                let res: Arc<dyn ChangesetFetcher + Send + Sync> = Arc::new(
-                    SimpleChangesetFetcher::new(changesets.clone(), repoid.clone()),
-                );
-                res
-            }
+        // Wrap the blobstore in `CensoredBlob` here, around the exact `redacted_blobs` `Arc`
+        // this `BlobRepo` stores below, instead of trusting that whatever built
+        // `blobstore_args` happened to consult the same `Arc` -- that was an unenforced
+        // caller convention before this wiring moved into `new_dangerous` itself.
+        let (blobstore, repoid) = {
+            cloned!(redacted_blobs);
+            RepoBlobstoreArgs::new_with_wrapped_inner_blobstore(
+                blobstore,
+                repoid,
+                move |inner| Arc::new(CensoredBlob::new(inner, redacted_blobs)) as Arc<dyn Blobstore>,
+            )
+            .into_blobrepo_parts()
         };
 
+        let changeset_fetcher_factory = build_changeset_fetcher_factory(&changesets, repoid);
+
         BlobRepo {
             bookmarks,
             blobstore,
@@ -133,13 +161,14 @@ impl BlobRepo {
             bonsai_git_mapping,
             bonsai_globalrev_mapping,
             repoid,
-            changeset_fetcher_factory: Arc::new(changeset_fetcher_factory),
+            changeset_fetcher_factory,
             derived_data_lease,
             filestore_config,
             phases_factory,
             derived_data_config,
             reponame,
             attributes,
+            redacted_blobs,
         }
     }
 
@@ -421,6 +450,18 @@ impl BlobRepo {
     pub fn get_derived_data_lease_ops(&self) -> Arc<dyn LeaseOps> {
         self.derived_data_lease.clone()
     }
+
+    /// The redaction set consulted by reads through `blobstore()`/`get_blobstore()` (see
+    /// `new_dangerous`, which wraps the blobstore in `CensoredBlob` around this exact `Arc`).
+    ///
+    /// This is a read-only snapshot: this crate doesn't own redacting/unredacting a key, since
+    /// that requires writing to the backing store behind `RedactedBlobs` (and, in a sharded
+    /// setup, notifying every other process reading through the same store). That belongs in
+    /// the redaction admin tooling, which updates the backing store directly; `RedactedBlobs`
+    /// itself is responsible for refreshing from it.
+    pub fn redacted_blobs(&self) -> &Arc<RedactedBlobs> {
+        &self.redacted_blobs
+    }
 }
 
 /// This function uploads bonsai changests object to blobstore in parallel, and then does
@@ -543,6 +584,37 @@ impl Clone for BlobRepo {
             derived_data_config: self.derived_data_config.clone(),
             reponame: self.reponame.clone(),
             attributes: self.attributes.clone(),
+            redacted_blobs: self.redacted_blobs.clone(),
+        }
+    }
+}
+
+impl DangerousOverride<Arc<RedactedBlobs>> for BlobRepo {
+    fn dangerous_override<F>(&self, modify: F) -> Self
+    where
+        F: FnOnce(Arc<RedactedBlobs>) -> Arc<RedactedBlobs>,
+    {
+        let redacted_blobs = modify(self.redacted_blobs.clone());
+
+        // Swapping the `redacted_blobs` field alone has no effect on reads: `blobstore()` reads
+        // through the `CensoredBlob` wrapper built in `new_dangerous`, which captured its own
+        // clone of the old `Arc` at construction time. Rebuild the blobstore the same way
+        // `new_dangerous` does, so the new redaction set is actually the one consulted.
+        let (blobstore, repoid) = {
+            cloned!(redacted_blobs);
+            RepoBlobstoreArgs::new_with_wrapped_inner_blobstore(
+                self.blobstore.clone(),
+                self.get_repoid(),
+                move |inner| Arc::new(CensoredBlob::new(inner, redacted_blobs)) as Arc<dyn Blobstore>,
+            )
+            .into_blobrepo_parts()
+        };
+
+        BlobRepo {
+            redacted_blobs,
+            repoid,
+            blobstore,
+            ..self.clone()
         }
     }
 }
@@ -613,20 +685,11 @@ impl DangerousOverride<Arc<dyn Changesets>> for BlobRepo {
         F: FnOnce(Arc<dyn Changesets>) -> Arc<dyn Changesets>,
     {
         let changesets = modify(self.changesets.clone());
-
-        let changeset_fetcher_factory = {
-            cloned!(changesets, self.repoid);
-            move || {
-                let res: Arc<dyn ChangesetFetcher + Send + Sync> = Arc::new(
-                    SimpleChangesetFetcher::new(changesets.clone(), repoid.clone()),
-                );
-                res
-            }
-        };
+        let changeset_fetcher_factory = build_changeset_fetcher_factory(&changesets, self.repoid);
 
         BlobRepo {
             changesets,
-            changeset_fetcher_factory: Arc::new(changeset_fetcher_factory),
+            changeset_fetcher_factory,
             ..self.clone()
         }
     }
@@ -672,3 +735,75 @@ impl DangerousOverride<FilestoreConfig> for BlobRepo {
         }
     }
 }
+
+/// A validated, non-"dangerous" way to assemble a `BlobRepo` with alternate component
+/// implementations. Unlike `DangerousOverride`, every swap goes through this single,
+/// type-checked construction path, so derived state (`changeset_fetcher_factory`, the
+/// `attributes` `TypeMap`) is rebuilt consistently and can't drift the way hand-rolled
+/// `DangerousOverride` impls could. Intended for production code that needs to assemble
+/// repos with alternate backends, not just tests.
+pub struct BlobRepoBuilder {
+    repo: BlobRepo,
+}
+
+impl BlobRepoBuilder {
+    pub fn new(repo: BlobRepo) -> Self {
+        BlobRepoBuilder { repo }
+    }
+
+    pub fn build(self) -> BlobRepo {
+        self.repo
+    }
+
+    /// Replaces the repo's underlying blobstore, re-wrapping it in `CensoredBlob` against the
+    /// repo's current redaction set so this "safe" path can't silently disable redaction
+    /// enforcement the way a caller discarding the existing wrapper would.
+    pub fn blobstore(mut self, blobstore: Arc<dyn Blobstore>) -> Self {
+        let redacted_blobs = self.repo.redacted_blobs().clone();
+        let (blobstore, repoid) = RepoBlobstoreArgs::new_with_wrapped_inner_blobstore(
+            blobstore,
+            self.repo.get_repoid(),
+            move |inner| Arc::new(CensoredBlob::new(inner, redacted_blobs)) as Arc<dyn Blobstore>,
+        )
+        .into_blobrepo_parts();
+        self.repo.blobstore = blobstore;
+        self.repo.repoid = repoid;
+        self
+    }
+
+    pub fn bookmarks(mut self, bookmarks: Arc<dyn Bookmarks>) -> Self {
+        self.repo.bookmarks = bookmarks;
+        self
+    }
+
+    pub fn changesets(mut self, changesets: Arc<dyn Changesets>) -> Self {
+        self.repo.changeset_fetcher_factory =
+            build_changeset_fetcher_factory(&changesets, self.repo.repoid);
+        self.repo.changesets = changesets;
+        self
+    }
+
+    pub fn filenodes(mut self, filenodes: Arc<dyn Filenodes>) -> Self {
+        let mut attrs = self.repo.attributes.as_ref().clone();
+        attrs.insert::<dyn Filenodes>(filenodes);
+        self.repo.attributes = Arc::new(attrs);
+        self
+    }
+
+    pub fn bonsai_hg_mapping(mut self, bonsai_hg_mapping: Arc<dyn BonsaiHgMapping>) -> Self {
+        let mut attrs = self.repo.attributes.as_ref().clone();
+        attrs.insert::<dyn BonsaiHgMapping>(bonsai_hg_mapping);
+        self.repo.attributes = Arc::new(attrs);
+        self
+    }
+
+    pub fn derived_data_config(mut self, derived_data_config: DerivedDataConfig) -> Self {
+        self.repo.derived_data_config = derived_data_config;
+        self
+    }
+
+    pub fn filestore_config(mut self, filestore_config: FilestoreConfig) -> Self {
+        self.repo.filestore_config = filestore_config;
+        self
+    }
+}
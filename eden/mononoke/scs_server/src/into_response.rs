@@ -7,7 +7,9 @@
 
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
+use std::collections::HashMap;
 use std::collections::HashSet;
+use std::path::Path;
 
 use async_trait::async_trait;
 use futures::future::try_join_all;
@@ -15,6 +17,7 @@ use futures::try_join;
 use itertools::Itertools;
 use maplit::btreemap;
 use mononoke_api::BookmarkInfo;
+use mononoke_api::BookmarkName;
 use mononoke_api::ChangesetContext;
 use mononoke_api::ChangesetId;
 use mononoke_api::ChangesetPathContentContext;
@@ -28,6 +31,7 @@ use mononoke_api::TreeEntry;
 use mononoke_api::TreeId;
 use mononoke_api::TreeSummary;
 use mononoke_api::UnifiedDiff;
+use mononoke_types::ContentId;
 use source_control as thrift;
 
 use crate::commit_id::map_commit_identities;
@@ -81,6 +85,9 @@ impl IntoResponse<thrift::TreeEntry> for (String, TreeEntry) {
                     id: dir.id().as_ref().to_vec(),
                     simple_format_sha1: summary.simple_format_sha1.as_ref().to_vec(),
                     simple_format_sha256: summary.simple_format_sha256.as_ref().to_vec(),
+                    // TODO: populate `simple_format_blake2b` once `TreeSummary` (in
+                    // `mononoke_types`, outside this crate) grows a blake2b digest field --
+                    // there's nothing on `TreeSummary` to read it from yet.
                     child_files_count: summary.child_files_count as i64,
                     child_files_total_size: summary.child_files_total_size as i64,
                     child_dirs_count: summary.child_dirs_count as i64,
@@ -96,6 +103,9 @@ impl IntoResponse<thrift::TreeEntry> for (String, TreeEntry) {
                     file_size: file.size() as i64,
                     content_sha1: file.content_sha1().as_ref().to_vec(),
                     content_sha256: file.content_sha256().as_ref().to_vec(),
+                    // TODO: populate `content_blake2b` once the file-entry type (in
+                    // `mononoke_types`, outside this crate) grows a blake2b digest accessor --
+                    // there's nothing here to read it from yet.
                     ..Default::default()
                 };
                 (
@@ -120,6 +130,9 @@ impl IntoResponse<thrift::FileInfo> for FileMetadata {
             file_size: self.total_size as i64,
             content_sha1: self.sha1.as_ref().to_vec(),
             content_sha256: self.sha256.as_ref().to_vec(),
+            // TODO: populate `content_blake2b` once `FileMetadata` (in `mononoke_types`,
+            // outside this crate) grows a blake2b digest field -- there's nothing on
+            // `FileMetadata` to read it from yet.
             ..Default::default()
         }
     }
@@ -132,6 +145,9 @@ impl IntoResponse<thrift::TreeInfo> for (TreeId, TreeSummary) {
             id: id.as_ref().to_vec(),
             simple_format_sha1: summary.simple_format_sha1.as_ref().to_vec(),
             simple_format_sha256: summary.simple_format_sha256.as_ref().to_vec(),
+            // TODO: populate `simple_format_blake2b` once `TreeSummary` (in `mononoke_types`,
+            // outside this crate) grows a blake2b digest field -- there's nothing on
+            // `TreeSummary` to read it from yet.
             child_files_count: summary.child_files_count as i64,
             child_files_total_size: summary.child_files_total_size as i64,
             child_dirs_count: summary.child_dirs_count as i64,
@@ -162,6 +178,175 @@ impl IntoResponse<thrift::Diff> for HeaderlessUnifiedDiff {
     }
 }
 
+/// Configuration for the rename/copy-detection pass over a changeset diff's added and
+/// removed paths, passed to `detect_renames`. No diff endpoint reads these options from its
+/// additional-data parameter; they take effect only where `detect_renames` is called.
+#[derive(Clone, Debug)]
+pub(crate) struct CopyDetectionOptions {
+    /// Whether to look for renames/copies at all. When disabled every path is reported as
+    /// an unrelated add or remove.
+    pub(crate) enabled: bool,
+    /// Minimum line-similarity ratio (0-100) for two non-identical blobs to be considered a
+    /// rename/copy pair.
+    pub(crate) similarity_threshold: u8,
+}
+
+impl Default for CopyDetectionOptions {
+    fn default() -> Self {
+        CopyDetectionOptions {
+            enabled: true,
+            similarity_threshold: 50,
+        }
+    }
+}
+
+/// The rename/copy source discovered for a single added path, and how confident the match is.
+#[derive(Clone, Debug)]
+pub(crate) struct CopyInfo {
+    pub(crate) from_path: String,
+    pub(crate) is_copy: bool,
+    pub(crate) similarity: u8,
+}
+
+/// Detect renames and copies between the removed and added paths of a changeset diff.
+///
+/// Mirrors the tree-diff rewrite tracking in gitoxide: an exact pass first matches any
+/// removed blob whose content id equals an added blob's id, emitting 100% renames (or
+/// copies, if the source path is still present on the new side). The remaining paths are
+/// then paired by a line-based similarity ratio (shared lines over the longer of the two
+/// blobs' line counts), greedily assigning the highest-scoring pairs above
+/// `options.similarity_threshold` so each path is used at most once.
+///
+/// `content` maps any path present in `removed` or `added` to its textual content; a path
+/// missing from `content` (e.g. a binary file) is only eligible for the exact-match pass.
+///
+/// `new_side_paths` is the full set of paths present in the destination changeset's manifest.
+/// A match is a copy (the source survives) rather than a rename (the source is gone) when the
+/// removed path is still in `new_side_paths` -- note this is *not* the same as being in
+/// `added`, since an unmodified file's path never appears in `removed`/`added` at all.
+pub(crate) fn detect_renames(
+    removed: &[(String, ContentId)],
+    added: &[(String, ContentId)],
+    new_side_paths: &HashSet<String>,
+    content: &HashMap<String, Vec<u8>>,
+    options: &CopyDetectionOptions,
+) -> HashMap<String, CopyInfo> {
+    let mut matches = HashMap::new();
+    if !options.enabled {
+        return matches;
+    }
+
+    let mut matched_removed: HashSet<&str> = HashSet::new();
+
+    for (add_path, add_id) in added {
+        if let Some((rem_path, _)) = removed.iter().find(|(_, rem_id)| rem_id == add_id) {
+            matched_removed.insert(rem_path.as_str());
+            matches.insert(
+                add_path.clone(),
+                CopyInfo {
+                    from_path: rem_path.clone(),
+                    is_copy: new_side_paths.contains(rem_path.as_str()),
+                    similarity: 100,
+                },
+            );
+        }
+    }
+
+    let unmatched_removed: Vec<&str> = removed
+        .iter()
+        .map(|(path, _)| path.as_str())
+        .filter(|path| !matched_removed.contains(path))
+        .collect();
+    let unmatched_added: Vec<&str> = added
+        .iter()
+        .map(|(path, _)| path.as_str())
+        .filter(|path| !matches.contains_key(*path))
+        .collect();
+
+    let mut candidates: Vec<(u8, &str, &str)> = Vec::new();
+    for &rem_path in &unmatched_removed {
+        let rem_content = match content.get(rem_path) {
+            Some(bytes) => bytes,
+            None => continue,
+        };
+        for &add_path in &unmatched_added {
+            let add_content = match content.get(add_path) {
+                Some(bytes) => bytes,
+                None => continue,
+            };
+            let similarity = line_similarity(rem_content, add_content);
+            if similarity >= options.similarity_threshold {
+                candidates.push((similarity, rem_path, add_path));
+            }
+        }
+    }
+    // Highest-similarity pairs first, so the greedy assignment below prefers the best match
+    // for each path.
+    candidates.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut used_removed: HashSet<&str> = HashSet::new();
+    let mut used_added: HashSet<&str> = HashSet::new();
+    for (similarity, rem_path, add_path) in candidates {
+        if used_removed.contains(rem_path) || used_added.contains(add_path) {
+            continue;
+        }
+        used_removed.insert(rem_path);
+        used_added.insert(add_path);
+        matches.insert(
+            add_path.to_string(),
+            CopyInfo {
+                from_path: rem_path.to_string(),
+                is_copy: new_side_paths.contains(rem_path),
+                similarity,
+            },
+        );
+    }
+
+    matches
+}
+
+/// Ratio of shared lines to the line count of the longer blob, as a percentage. A repeated
+/// line counts as shared as many times as it occurs in both blobs.
+fn line_similarity(a: &[u8], b: &[u8]) -> u8 {
+    let mut counts_a: HashMap<&[u8], usize> = HashMap::new();
+    let mut lines_a = 0;
+    for line in a.split(|&byte| byte == b'\n') {
+        *counts_a.entry(line).or_insert(0) += 1;
+        lines_a += 1;
+    }
+    let mut lines_b = 0;
+    let mut shared = 0;
+    for line in b.split(|&byte| byte == b'\n') {
+        lines_b += 1;
+        if let Some(count) = counts_a.get_mut(line).filter(|count| **count > 0) {
+            *count -= 1;
+            shared += 1;
+        }
+    }
+    let max_lines: usize = lines_a.max(lines_b);
+    if max_lines == 0 {
+        return 100;
+    }
+    ((shared * 100) / max_lines) as u8
+}
+
+impl IntoResponse<thrift::Diff> for (UnifiedDiff, Option<CopyInfo>) {
+    fn into_response(self) -> thrift::Diff {
+        let (diff, copy_info) = self;
+        let (copy_from_path, copy_from_similarity) = match copy_info {
+            Some(info) => (Some(info.from_path), Some(info.similarity as i64)),
+            None => (None, None),
+        };
+        thrift::Diff::raw_diff(thrift::RawDiff {
+            raw_diff: Some(diff.raw_diff),
+            is_binary: diff.is_binary,
+            copy_from_path,
+            copy_from_similarity,
+            ..Default::default()
+        })
+    }
+}
+
 #[async_trait]
 impl AsyncIntoResponse<Option<thrift::FilePathInfo>> for ChangesetPathContentContext {
     async fn into_response(self) -> Result<Option<thrift::FilePathInfo>, errors::ServiceError> {
@@ -208,6 +393,365 @@ impl AsyncIntoResponse<Option<thrift::TreePathInfo>> for ChangesetPathContentCon
     }
 }
 
+/// Compression to apply when rendering a tree as a downloadable archive.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ArchiveFormat {
+    Tar,
+    TarGz,
+}
+
+/// Additional data needed to render a tree as a downloadable archive: the format, and an
+/// optional path prefix under which every entry is nested (the way GitHub's codeload
+/// archives nest everything under `<repo>-<ref>/`).
+#[derive(Clone, Debug)]
+pub(crate) struct TreeArchiveOptions {
+    pub(crate) format: ArchiveFormat,
+    pub(crate) prefix: Option<String>,
+}
+
+#[async_trait]
+impl AsyncIntoResponseWith<thrift::TreeArchive> for ChangesetPathContentContext {
+    /// The additional data selects the archive format and an optional path prefix.
+    type Additional = TreeArchiveOptions;
+
+    async fn into_response_with(
+        self,
+        additional: &TreeArchiveOptions,
+    ) -> Result<thrift::TreeArchive, errors::ServiceError> {
+        let archive = match additional.format {
+            ArchiveFormat::Tar => {
+                let mut builder = tar::Builder::new(Vec::new());
+                append_path_to_archive(&mut builder, &self, additional.prefix.as_deref()).await?;
+                builder
+                    .into_inner()
+                    .map_err(|e| errors::internal_error(e).into())?
+            }
+            ArchiveFormat::TarGz => {
+                let encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                let mut builder = tar::Builder::new(encoder);
+                append_path_to_archive(&mut builder, &self, additional.prefix.as_deref()).await?;
+                builder
+                    .into_inner()
+                    .map_err(|e| errors::internal_error(e).into())?
+                    .finish()
+                    .map_err(|e| errors::internal_error(e).into())?
+            }
+        };
+        Ok(thrift::TreeArchive {
+            archive,
+            ..Default::default()
+        })
+    }
+}
+
+/// Recursively walk `path` (a file or a tree), writing one tar entry per file with the mode
+/// derived from its `FileType` — symlink entries carry the link target rather than content,
+/// the way rgit assembles repository snapshots with `tar::Builder` wrapped in a `GzEncoder`.
+#[async_recursion::async_recursion]
+async fn append_path_to_archive<W: std::io::Write + Send>(
+    builder: &mut tar::Builder<W>,
+    path: &ChangesetPathContentContext,
+    prefix: Option<&str>,
+) -> Result<(), errors::ServiceError> {
+    if let Some(tree) = path.tree().await? {
+        for (name, _entry) in tree.list().await? {
+            append_path_to_archive(builder, &path.child(name), prefix).await?;
+        }
+        return Ok(());
+    }
+
+    let file = match path.file().await? {
+        Some(file) => file,
+        None => return Ok(()),
+    };
+    let file_type = path.file_type().await?.unwrap_or(FileType::Regular);
+    let content = file.content_concat().await?;
+    let archive_path = match prefix {
+        Some(prefix) => format!("{}/{}", prefix, path.path()),
+        None => path.path().to_string(),
+    };
+
+    let mut header = tar::Header::new_gnu();
+    header.set_mode(match file_type {
+        FileType::Executable => 0o755,
+        _ => 0o644,
+    });
+
+    if file_type == FileType::Symlink {
+        // Symlink entries carry the link target in the header itself, not a data section, so
+        // `size` must be 0 -- leaving it set to the target length desyncs every entry after
+        // this one, since `append_link` writes no body bytes to match.
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        header.set_cksum();
+        let target = String::from_utf8_lossy(&content).into_owned();
+        builder
+            .append_link(&mut header, &archive_path, &target)
+            .map_err(|e| errors::internal_error(e).into())?;
+    } else {
+        header.set_size(content.len() as u64);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, &archive_path, &*content)
+            .map_err(|e| errors::internal_error(e).into())?;
+    }
+    Ok(())
+}
+
+/// Caps on how much of a file to syntax-highlight, to bound server work on huge files.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct HighlightOptions {
+    pub(crate) max_lines: Option<usize>,
+    pub(crate) max_bytes: Option<usize>,
+}
+
+#[async_trait]
+impl AsyncIntoResponseWith<Option<thrift::HighlightedFile>> for ChangesetPathContentContext {
+    /// The additional data caps how many lines/bytes are tokenized, to bound server work on
+    /// huge files.
+    type Additional = HighlightOptions;
+
+    async fn into_response_with(
+        self,
+        additional: &HighlightOptions,
+    ) -> Result<Option<thrift::HighlightedFile>, errors::ServiceError> {
+        let file = match self.file().await? {
+            Some(file) => file,
+            None => return Ok(None),
+        };
+        let content = file.content_concat().await?;
+        let content = match additional.max_bytes {
+            Some(max_bytes) if content.len() > max_bytes => &content[..max_bytes],
+            _ => &content[..],
+        };
+
+        if looks_binary(content) {
+            return Ok(Some(thrift::HighlightedFile {
+                is_binary: true,
+                ..Default::default()
+            }));
+        }
+
+        let language = detect_language(&self.path().to_string(), content);
+        let text = String::from_utf8_lossy(content);
+        let lines = text
+            .lines()
+            .take(additional.max_lines.unwrap_or(usize::MAX))
+            .map(|line| thrift::HighlightedLine {
+                segments: highlight_line(line, language)
+                    .into_iter()
+                    .map(|(style_class, text)| thrift::HighlightedSegment {
+                        style_class: style_class.to_string(),
+                        text,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            })
+            .collect();
+
+        Ok(Some(thrift::HighlightedFile {
+            language: language.name().to_string(),
+            lines,
+            is_binary: false,
+            ..Default::default()
+        }))
+    }
+}
+
+/// A file's detected source language: resolves the keyword table used for tokenization and
+/// is reported to clients as-is.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    C,
+    Shell,
+    PlainText,
+}
+
+impl Language {
+    fn name(self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Python => "python",
+            Language::JavaScript => "javascript",
+            Language::C => "c",
+            Language::Shell => "shell",
+            Language::PlainText => "plaintext",
+        }
+    }
+
+    fn keywords(self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &[
+                "fn", "let", "mut", "pub", "struct", "enum", "impl", "trait", "match", "if",
+                "else", "for", "while", "loop", "return", "use", "mod", "async", "await",
+            ],
+            Language::Python => &[
+                "def", "class", "import", "from", "return", "if", "elif", "else", "for", "while",
+                "with", "as", "try", "except", "lambda", "yield",
+            ],
+            Language::JavaScript => &[
+                "function", "const", "let", "var", "return", "if", "else", "for", "while",
+                "class", "import", "export", "async", "await",
+            ],
+            Language::C => &[
+                "int", "char", "void", "struct", "if", "else", "for", "while", "return", "static",
+                "const", "typedef",
+            ],
+            Language::Shell => &[
+                "if", "then", "else", "fi", "for", "do", "done", "while", "function", "echo",
+            ],
+            Language::PlainText => &[],
+        }
+    }
+
+    fn line_comment(self) -> Option<&'static str> {
+        match self {
+            Language::Rust | Language::JavaScript | Language::C => Some("//"),
+            Language::Python | Language::Shell => Some("#"),
+            Language::PlainText => None,
+        }
+    }
+}
+
+/// Resolve a language from the path's extension, falling back to sniffing a shebang line in
+/// the content when the extension is missing or unrecognised.
+fn detect_language(path: &str, content: &[u8]) -> Language {
+    match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("rs") => return Language::Rust,
+        Some("py") => return Language::Python,
+        Some("js") | Some("jsx") | Some("mjs") => return Language::JavaScript,
+        Some("c") | Some("h") => return Language::C,
+        Some("sh") | Some("bash") => return Language::Shell,
+        _ => {}
+    }
+    if content.starts_with(b"#!") {
+        let first_line = content.split(|&b| b == b'\n').next().unwrap_or(&[]);
+        let first_line = String::from_utf8_lossy(first_line);
+        if first_line.contains("python") {
+            return Language::Python;
+        } else if first_line.contains("bash") || first_line.ends_with("sh") {
+            return Language::Shell;
+        } else if first_line.contains("node") {
+            return Language::JavaScript;
+        }
+    }
+    Language::PlainText
+}
+
+/// Heuristic binary sniff matching the one Git uses: a NUL byte in the first few KiB means
+/// the blob isn't text.
+fn looks_binary(content: &[u8]) -> bool {
+    const SNIFF_LEN: usize = 8000;
+    content[..content.len().min(SNIFF_LEN)].contains(&0)
+}
+
+/// Tokenize a single line into `(style_class, text)` spans: `"com"` for a trailing line
+/// comment, `"str"` for quoted strings, `"num"` for numeric literals, `"kw"` for the
+/// language's keywords, and `"pln"` for everything else (including whitespace and
+/// punctuation, emitted verbatim so clients can reassemble the original line).
+fn highlight_line(line: &str, language: Language) -> Vec<(&'static str, String)> {
+    if let Some(marker) = language.line_comment() {
+        if let Some(pos) = line.find(marker) {
+            let mut spans = tokenize_code(&line[..pos], language);
+            spans.push(("com", line[pos..].to_string()));
+            return spans;
+        }
+    }
+    tokenize_code(line, language)
+}
+
+fn tokenize_code(code: &str, language: Language) -> Vec<(&'static str, String)> {
+    let mut spans: Vec<(&'static str, String)> = Vec::new();
+    let mut chars = code.chars().peekable();
+    let mut word = String::new();
+
+    while let Some(&ch) = chars.peek() {
+        if ch == '"' || ch == '\'' {
+            flush_word(&mut word, language, &mut spans);
+            let quote = ch;
+            let mut string_lit = String::new();
+            string_lit.push(ch);
+            chars.next();
+            while let Some(&c) = chars.peek() {
+                string_lit.push(c);
+                chars.next();
+                if c == quote {
+                    break;
+                }
+            }
+            spans.push(("str", string_lit));
+        } else if ch.is_alphanumeric() || ch == '_' {
+            word.push(ch);
+            chars.next();
+        } else {
+            flush_word(&mut word, language, &mut spans);
+            // Coalesce a run of punctuation/whitespace into one span instead of emitting a
+            // separate span per character -- for ordinary source this is most of the bytes in
+            // the file, so merging keeps segment count (and the per-segment thrift/String
+            // allocation) from scaling with file size.
+            let mut plain = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_alphanumeric() || c == '_' || c == '"' || c == '\'' {
+                    break;
+                }
+                plain.push(c);
+                chars.next();
+            }
+            spans.push(("pln", plain));
+        }
+    }
+    flush_word(&mut word, language, &mut spans);
+    spans
+}
+
+fn flush_word(word: &mut String, language: Language, spans: &mut Vec<(&'static str, String)>) {
+    if word.is_empty() {
+        return;
+    }
+    let style = if word.chars().next().unwrap().is_ascii_digit() {
+        "num"
+    } else if language.keywords().contains(&word.as_str()) {
+        "kw"
+    } else {
+        "pln"
+    };
+    spans.push((style, std::mem::take(word)));
+}
+
+/// Key for the `CommitInfo` response cache: the repo, the changeset within that repo, and the
+/// exact set of identity schemes that were requested (a different scheme set needs a different
+/// response). `ChangesetId` is a content hash and isn't scoped to one repo, so two repos with
+/// byte-identical commit content would otherwise collide and serve each other's identity
+/// mappings -- `repo_name` is required for the same reason `BookmarkInfoCacheKey` below needs it.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct CommitInfoCacheKey {
+    repo_name: String,
+    changeset_id: ChangesetId,
+    identity_schemes: BTreeSet<thrift::CommitIdentityScheme>,
+}
+
+/// Process-local, time-to-live cache of fully-built `thrift::CommitInfo` responses, as rgit
+/// does for its rendered commits. Fan-out calls that look up the same changeset and scheme
+/// set repeatedly (e.g. listing parents across many commits) collapse to one computation per
+/// distinct key.
+static COMMIT_INFO_CACHE: once_cell::sync::Lazy<moka::future::Cache<CommitInfoCacheKey, thrift::CommitInfo>> =
+    once_cell::sync::Lazy::new(|| {
+        moka::future::Cache::builder()
+            .max_capacity(COMMIT_INFO_CACHE_CAPACITY)
+            .time_to_live(COMMIT_INFO_CACHE_TTL)
+            .build()
+    });
+
+// TODO: source these from repo config instead of hardcoding, once there's a config knob for
+// per-process response caching.
+const COMMIT_INFO_CACHE_CAPACITY: u64 = 100_000;
+const COMMIT_INFO_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
 #[async_trait]
 impl AsyncIntoResponseWith<thrift::CommitInfo> for ChangesetContext {
     /// The additional data is the set of commit identity schemes to be
@@ -218,6 +762,15 @@ impl AsyncIntoResponseWith<thrift::CommitInfo> for ChangesetContext {
         self,
         identity_schemes: &BTreeSet<thrift::CommitIdentityScheme>,
     ) -> Result<thrift::CommitInfo, errors::ServiceError> {
+        let cache_key = CommitInfoCacheKey {
+            repo_name: self.repo().name().to_string(),
+            changeset_id: self.id(),
+            identity_schemes: identity_schemes.clone(),
+        };
+        if let Some(cached) = COMMIT_INFO_CACHE.get(&cache_key).await {
+            return Ok(cached);
+        }
+
         async fn map_parent_identities(
             changeset: &ChangesetContext,
             identity_schemes: &BTreeSet<thrift::CommitIdentityScheme>,
@@ -246,7 +799,7 @@ impl AsyncIntoResponseWith<thrift::CommitInfo> for ChangesetContext {
             self.extras(),
             self.generation(),
         )?;
-        Ok(thrift::CommitInfo {
+        let commit_info = thrift::CommitInfo {
             ids,
             message,
             date: date.timestamp(),
@@ -256,7 +809,11 @@ impl AsyncIntoResponseWith<thrift::CommitInfo> for ChangesetContext {
             extra: extra.into_iter().collect(),
             generation: generation.value() as i64,
             ..Default::default()
-        })
+        };
+        COMMIT_INFO_CACHE
+            .insert(cache_key, commit_info.clone())
+            .await;
+        Ok(commit_info)
     }
 }
 
@@ -405,3 +962,57 @@ impl AsyncIntoResponseWith<thrift::BookmarkInfo> for BookmarkInfo {
         })
     }
 }
+
+/// Key for the `BookmarkInfo` response cache: the repo, the bookmark name within that repo, and
+/// the exact set of identity schemes that were requested, mirroring `CommitInfoCacheKey` above
+/// -- this is a multi-tenant server, so two repos can share a bookmark name (e.g. `master`), and
+/// two callers can request different identity schemes for the same bookmark.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct BookmarkInfoCacheKey {
+    repo_name: String,
+    bookmark_name: String,
+    identity_schemes: BTreeSet<thrift::CommitIdentityScheme>,
+}
+
+/// Process-local, short-time-to-live cache of `thrift::BookmarkInfo` responses keyed on
+/// `BookmarkInfoCacheKey`. Bookmarks move more often than commits are rewritten, so this cache
+/// uses a much shorter TTL than `COMMIT_INFO_CACHE`.
+static BOOKMARK_INFO_CACHE: once_cell::sync::Lazy<
+    moka::future::Cache<BookmarkInfoCacheKey, thrift::BookmarkInfo>,
+> = once_cell::sync::Lazy::new(|| {
+    moka::future::Cache::builder()
+        .max_capacity(BOOKMARK_INFO_CACHE_CAPACITY)
+        .time_to_live(BOOKMARK_INFO_CACHE_TTL)
+        .build()
+});
+
+const BOOKMARK_INFO_CACHE_CAPACITY: u64 = 10_000;
+const BOOKMARK_INFO_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+#[async_trait]
+impl AsyncIntoResponseWith<thrift::BookmarkInfo> for (BookmarkName, BookmarkInfo) {
+    /// The additional data is the set of commit identity schemes to be
+    /// returned in the response.
+    type Additional = BTreeSet<thrift::CommitIdentityScheme>;
+
+    async fn into_response_with(
+        self,
+        identity_schemes: &BTreeSet<thrift::CommitIdentityScheme>,
+    ) -> Result<thrift::BookmarkInfo, errors::ServiceError> {
+        let (name, info) = self;
+        let cache_key = BookmarkInfoCacheKey {
+            repo_name: info.warm_changeset.repo().name().to_string(),
+            bookmark_name: name.to_string(),
+            identity_schemes: identity_schemes.clone(),
+        };
+        if let Some(cached) = BOOKMARK_INFO_CACHE.get(&cache_key).await {
+            return Ok(cached);
+        }
+
+        let bookmark_info = info.into_response_with(identity_schemes).await?;
+        BOOKMARK_INFO_CACHE
+            .insert(cache_key, bookmark_info.clone())
+            .await;
+        Ok(bookmark_info)
+    }
+}
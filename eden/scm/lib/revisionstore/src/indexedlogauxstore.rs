@@ -6,14 +6,20 @@
  */
 
 use std::{
+    collections::{BTreeMap, HashMap, HashSet},
     io::{Cursor, Read, Write},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
-use anyhow::{bail, Result};
-use byteorder::{ReadBytesExt, WriteBytesExt};
+use anyhow::{anyhow, bail, Context, Result};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use chacha20poly1305::{
+    aead::{Aead, NewAead, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
 use minibytes::Bytes;
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use rand::{rngs::OsRng, RngCore};
 
 use configparser::{config::ConfigSet, convert::ByteCount};
 use edenapi_types::{ContentId, FileAuxData, Sha1, Sha256};
@@ -23,6 +29,16 @@ use vlqencoding::{VLQDecode, VLQEncode};
 
 use crate::indexedlogutil::{Store, StoreOpenOptions, StoreType};
 
+/// Version 1 optional-digest bitmask bits. Stored as a VLQ so the bitmask itself can grow
+/// past 8 bits without another format bump.
+const OPT_CRC32: u64 = 1 << 0;
+const OPT_BLAKE3: u64 = 1 << 1;
+
+/// Version byte indicating the body is ChaCha20-Poly1305-encrypted rather than plaintext. See
+/// `Entry::serialize`/`Entry::deserialize` for the framing.
+const VERSION_ENCRYPTED: u8 = 2;
+const ENCRYPTION_NONCE_LEN: usize = 12;
+
 /// See edenapi_types::FileAuxData and mononoke_types::ContentMetadata
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Entry {
@@ -30,6 +46,8 @@ pub struct Entry {
     content_id: ContentId,
     content_sha1: Sha1,
     content_sha256: Sha256,
+    content_crc32: Option<u32>,
+    content_blake3: Option<[u8; 32]>,
 }
 
 impl From<FileAuxData> for Entry {
@@ -39,6 +57,8 @@ impl From<FileAuxData> for Entry {
             content_id: v.content_id,
             content_sha1: v.sha1,
             content_sha256: v.sha256,
+            content_crc32: None,
+            content_blake3: None,
         }
     }
 }
@@ -60,6 +80,14 @@ impl Entry {
         self.content_sha256
     }
 
+    pub fn content_crc32(&self) -> Option<u32> {
+        self.content_crc32
+    }
+
+    pub fn content_blake3(&self) -> Option<[u8; 32]> {
+        self.content_blake3
+    }
+
     /// Serialize the Entry to Bytes.
     ///
     /// The serialization format is as follows:
@@ -69,25 +97,125 @@ impl Entry {
     /// - content sha1 <20 bytes>
     /// - content sha256 <32 bytes>
     /// - total_size <u64 VLQ, 1-9 bytes>
-    fn serialize(&self, hgid: HgId) -> Result<Bytes> {
+    ///
+    /// Version 1 appends, only when at least one optional digest is set:
+    /// - optional-digest bitmask <u64 VLQ, 1-9 bytes>
+    /// - content crc32 <4 bytes>, if `OPT_CRC32` is set in the bitmask
+    /// - content blake3 <32 bytes>, if `OPT_BLAKE3` is set in the bitmask
+    ///
+    /// When `key` is set, everything below the `HgId` is instead encrypted: the outer version
+    /// byte becomes `VERSION_ENCRYPTED`, followed by a random 12-byte nonce and then the
+    /// ChaCha20-Poly1305 ciphertext (with its 16-byte tag appended) of the body above, i.e. the
+    /// real version byte onward. The `HgId` is passed as AEAD associated data so a ciphertext
+    /// can't be replayed under a different `HgId`.
+    fn serialize(&self, hgid: HgId, key: Option<&[u8; 32]>) -> Result<Bytes> {
+        let body = self.serialize_body()?;
+
         let mut buf = Vec::new();
         buf.write_all(hgid.as_ref())?;
-        buf.write_u8(0)?; // write version
+
+        match key {
+            None => buf.write_all(&body)?,
+            Some(key) => {
+                buf.write_u8(VERSION_ENCRYPTED)?;
+
+                let mut nonce_bytes = [0u8; ENCRYPTION_NONCE_LEN];
+                OsRng.fill_bytes(&mut nonce_bytes);
+
+                let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+                let ciphertext = cipher
+                    .encrypt(
+                        Nonce::from_slice(&nonce_bytes),
+                        Payload {
+                            msg: &body,
+                            aad: hgid.as_ref(),
+                        },
+                    )
+                    .map_err(|_| anyhow!("failed to encrypt auxstore entry"))?;
+
+                buf.write_all(&nonce_bytes)?;
+                buf.write_all(&ciphertext)?;
+            }
+        }
+
+        Ok(buf.into())
+    }
+
+    /// The version byte (0 if no optional digests are set, 1 otherwise) followed by the content
+    /// fields, i.e. everything that sits after the `HgId` in the plaintext format. This is the
+    /// portion that gets AEAD-encrypted when an encryption key is configured.
+    fn serialize_body(&self) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+
+        let mut optional_digests = 0u64;
+        if self.content_crc32.is_some() {
+            optional_digests |= OPT_CRC32;
+        }
+        if self.content_blake3.is_some() {
+            optional_digests |= OPT_BLAKE3;
+        }
+
+        buf.write_u8(if optional_digests == 0 { 0 } else { 1 })?;
         buf.write_all(self.content_id.as_ref())?;
         buf.write_all(self.content_sha1.as_ref())?;
         buf.write_all(self.content_sha256.as_ref())?;
         buf.write_vlq(self.total_size)?;
-        Ok(buf.into())
+
+        if optional_digests != 0 {
+            buf.write_vlq(optional_digests)?;
+            if let Some(crc32) = self.content_crc32 {
+                buf.write_u32::<LittleEndian>(crc32)?;
+            }
+            if let Some(blake3) = self.content_blake3 {
+                buf.write_all(&blake3)?;
+            }
+        }
+
+        Ok(buf)
     }
 
-    fn deserialize(bytes: Bytes) -> Result<(HgId, Self)> {
+    fn deserialize(bytes: Bytes, key: Option<&[u8; 32]>) -> Result<(HgId, Self)> {
         let data: &[u8] = bytes.as_ref();
         let mut cur = Cursor::new(data);
 
         let hgid = cur.read_hgid()?;
+        let version = cur.read_u8()?;
+
+        let body: Vec<u8> = if version == VERSION_ENCRYPTED {
+            let key = key.ok_or_else(|| {
+                anyhow!("auxstore entry for {} is encrypted but no encryption key is configured", hgid)
+            })?;
+
+            let mut nonce_bytes = [0u8; ENCRYPTION_NONCE_LEN];
+            cur.read_exact(&mut nonce_bytes)?;
+
+            let mut ciphertext = Vec::new();
+            cur.read_to_end(&mut ciphertext)?;
+
+            let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+            cipher
+                .decrypt(
+                    Nonce::from_slice(&nonce_bytes),
+                    Payload {
+                        msg: &ciphertext,
+                        aad: hgid.as_ref(),
+                    },
+                )
+                .map_err(|_| anyhow!("failed to decrypt auxstore entry: wrong key or corrupted data"))?
+        } else {
+            let mut body = vec![version];
+            cur.read_to_end(&mut body)?;
+            body
+        };
+
+        Self::deserialize_body(hgid, &body)
+    }
+
+    fn deserialize_body(hgid: HgId, body: &[u8]) -> Result<(HgId, Self)> {
+        let mut cur = Cursor::new(body);
 
         let version = cur.read_u8()?;
-        if version != 0 {
+        if version > 1 {
             bail!("unsupported auxstore entry version {}", version);
         }
 
@@ -102,6 +230,20 @@ impl Entry {
 
         let total_size: u64 = cur.read_vlq()?;
 
+        let mut content_crc32 = None;
+        let mut content_blake3 = None;
+        if version == 1 {
+            let optional_digests: u64 = cur.read_vlq()?;
+            if optional_digests & OPT_CRC32 != 0 {
+                content_crc32 = Some(cur.read_u32::<LittleEndian>()?);
+            }
+            if optional_digests & OPT_BLAKE3 != 0 {
+                let mut blake3 = [0u8; 32];
+                cur.read_exact(&mut blake3)?;
+                content_blake3 = Some(blake3);
+            }
+        }
+
         Ok((
             hgid,
             Entry {
@@ -109,49 +251,264 @@ impl Entry {
                 content_sha1: content_sha1.into(),
                 content_sha256: content_sha256.into(),
                 total_size,
+                content_crc32,
+                content_blake3,
             },
         ))
     }
 }
 
-pub struct AuxStoreInner(Store);
+/// A standard Bloom filter over the `HgId`s stored in an `AuxStore`, used to make a miss in
+/// `AuxStoreInner::get` near-free: if any of the `k` bits derived from a key is unset, the key
+/// was definitely never stored, so the caller can skip the indexedlog index lookup entirely.
+/// The filter is rebuilt by scanning the log on open and is never persisted, which is only free
+/// of false negatives for a store this process has exclusive write access to. For
+/// `StoreType::Shared` -- the common multi-process revisionstore cache -- a sibling process can
+/// append entries to the on-disk log after this instance opened; this in-memory filter never
+/// learns about them, so a stale filter would wrongly report "definitely absent" for hgids the
+/// other process already flushed. `AuxStore::new` therefore only builds this filter for
+/// `StoreType::Local` stores, where no other process writes concurrently.
+struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+}
+
+impl BloomFilter {
+    /// Size the filter from the expected entry count `n` and a target false-positive rate
+    /// `p`, using the standard formulas `m = -n·ln(p)/(ln2)²` for the bit array size and
+    /// `k = round((m/n)·ln2)` for the number of hash functions.
+    fn with_capacity(expected_entries: usize, false_positive_rate: f64) -> Self {
+        let n = (expected_entries.max(1)) as f64;
+        let p = false_positive_rate.max(f64::MIN_POSITIVE).min(0.5);
+        let num_bits = ((-(n * p.ln())) / std::f64::consts::LN_2.powi(2))
+            .ceil()
+            .max(64.0) as u64;
+        let num_hashes = (((num_bits as f64) / n) * std::f64::consts::LN_2)
+            .round()
+            .max(1.0) as u32;
+        BloomFilter {
+            bits: vec![0u64; ((num_bits + 63) / 64) as usize],
+            num_bits,
+            num_hashes,
+        }
+    }
+
+    /// Split the 20-byte `HgId` into two 64-bit lanes, combined below as `h1 + i·h2` to
+    /// derive `k` independent-enough bit positions from a single pair of hashes.
+    fn lanes(hgid: &HgId) -> (u64, u64) {
+        let bytes = hgid.as_ref();
+        let mut h1 = [0u8; 8];
+        let mut h2 = [0u8; 8];
+        h1.copy_from_slice(&bytes[0..8]);
+        h2.copy_from_slice(&bytes[8..16]);
+        (u64::from_le_bytes(h1), u64::from_le_bytes(h2))
+    }
+
+    fn bit_indexes(&self, hgid: &HgId) -> Vec<u64> {
+        let (h1, h2) = Self::lanes(hgid);
+        (0..self.num_hashes as u64)
+            .map(|i| h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits)
+            .collect()
+    }
+
+    fn set(&mut self, hgid: &HgId) {
+        for idx in self.bit_indexes(hgid) {
+            self.bits[(idx / 64) as usize] |= 1 << (idx % 64);
+        }
+    }
+
+    fn might_contain(&self, hgid: &HgId) -> bool {
+        self.bit_indexes(hgid)
+            .into_iter()
+            .all(|idx| self.bits[(idx / 64) as usize] & (1 << (idx % 64)) != 0)
+    }
+}
+
+pub struct AuxStoreInner {
+    log: Store,
+    bloom: Option<BloomFilter>,
+    /// At-rest encryption key for entry bodies, if `indexedlog.aux.encryption-key-path` is
+    /// configured. See `Entry::serialize`/`Entry::deserialize`.
+    key: Option<[u8; 32]>,
+}
 
 impl AuxStoreInner {
     pub fn get(&self, hgid: HgId) -> Result<Option<Entry>> {
-        let mut entries = self.0.lookup(0, &hgid)?;
+        if let Some(bloom) = &self.bloom {
+            if !bloom.might_contain(&hgid) {
+                return Ok(None);
+            }
+        }
+
+        let mut entries = self.log.lookup(0, &hgid)?;
 
         let slice = match entries.next() {
             None => return Ok(None),
             Some(slice) => slice?,
         };
-        let bytes = self.0.slice_to_bytes(slice);
+        let bytes = self.log.slice_to_bytes(slice);
 
-        Entry::deserialize(bytes).map(|(_hgid, entry)| Some(entry))
+        Entry::deserialize(bytes, self.key.as_ref()).map(|(_hgid, entry)| Some(entry))
     }
 
     pub fn put(&mut self, hgid: HgId, entry: &Entry) -> Result<()> {
-        self.0.append(&entry.serialize(hgid)?)?;
+        self.log.append(&entry.serialize(hgid, self.key.as_ref())?)?;
+        if let Some(bloom) = &mut self.bloom {
+            bloom.set(&hgid);
+        }
         Ok(())
     }
 
     pub fn flush(&mut self) -> Result<()> {
-        self.0.flush()?;
+        self.log.flush()?;
         Ok(())
     }
 
+    /// Filter `hgids` down to those that might be present, using the Bloom filter (if any) to
+    /// cheaply drop known-missing keys without touching the log at all.
+    fn contains_many<'a>(&self, hgids: &'a [HgId]) -> Vec<&'a HgId> {
+        match &self.bloom {
+            Some(bloom) => hgids.iter().filter(|hgid| bloom.might_contain(hgid)).collect(),
+            None => hgids.iter().collect(),
+        }
+    }
+
+    /// Look up many hgids under a single guard acquisition, yielding only the ones found. Keys
+    /// the Bloom filter proves absent never reach the log.
+    pub fn get_many_stream<'a>(
+        &'a self,
+        hgids: &'a [HgId],
+    ) -> impl Iterator<Item = Result<(HgId, Entry)>> + 'a {
+        self.contains_many(hgids).into_iter().filter_map(move |hgid| {
+            let mut entries = match self.log.lookup(0, hgid) {
+                Ok(entries) => entries,
+                Err(e) => return Some(Err(e)),
+            };
+            let slice = match entries.next() {
+                None => return None,
+                Some(Ok(slice)) => slice,
+                Some(Err(e)) => return Some(Err(e)),
+            };
+            let bytes = self.log.slice_to_bytes(slice);
+            Some(Entry::deserialize(bytes, self.key.as_ref()))
+        })
+    }
+
+    /// Look up many hgids under a single guard acquisition, returning only the ones found.
+    pub fn get_many(&self, hgids: &[HgId]) -> Result<HashMap<HgId, Entry>> {
+        self.get_many_stream(hgids).collect()
+    }
+
     pub(crate) fn hgids(&self) -> Result<Vec<HgId>> {
-        let inner = &self.0;
-        inner
-            .iter()
+        let log = &self.log;
+        log.iter()
             .map(|slice| {
-                let bytes = inner.slice_to_bytes(slice?);
-                Entry::deserialize(bytes).map(|(hgid, _entry)| hgid)
+                let bytes = log.slice_to_bytes(slice?);
+                Entry::deserialize(bytes, self.key.as_ref()).map(|(hgid, _entry)| hgid)
             })
             .collect()
     }
+
+    /// Walk the log once and compute aggregate entry/size statistics. `indexedlog` is
+    /// append-only, so `put` can append the same hgid multiple times; `duplicate_hgids`
+    /// counts those re-appends, which is useful for deciding when to compact.
+    pub(crate) fn stats(&self) -> Result<AuxStoreStats> {
+        let mut seen = HashSet::new();
+        let mut stats = AuxStoreStats::default();
+
+        for slice in self.log.iter() {
+            let bytes = self.log.slice_to_bytes(slice?);
+            let (hgid, entry) = Entry::deserialize(bytes, self.key.as_ref())?;
+
+            stats.live_entries += 1;
+            if !seen.insert(hgid) {
+                stats.duplicate_hgids += 1;
+            }
+            stats.total_content_size += entry.total_size;
+            stats.largest_entry = stats.largest_entry.max(entry.total_size);
+            *stats.size_histogram.entry(size_bucket(entry.total_size)).or_insert(0) += 1;
+        }
+        stats.distinct_hgids = seen.len();
+
+        Ok(stats)
+    }
+}
+
+/// Power-of-two bucket for an entry's `total_size`, used to build a coarse size histogram:
+/// bucket `b` holds entries with `total_size` in `(2^(b-1), 2^b]` (bucket `0` holds entries of
+/// size 0 and 1).
+fn size_bucket(size: u64) -> u32 {
+    if size == 0 {
+        0
+    } else {
+        64 - (size - 1).leading_zeros()
+    }
 }
 
-pub struct AuxStore(RwLock<AuxStoreInner>);
+/// Aggregate statistics about an `AuxStore`, computed by walking the log once.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AuxStoreStats {
+    /// Number of entries in the log, including duplicate re-appends of the same hgid.
+    pub live_entries: usize,
+    /// Number of distinct hgids among those entries.
+    pub distinct_hgids: usize,
+    /// Number of entries whose hgid already appeared earlier in the log.
+    pub duplicate_hgids: usize,
+    /// Sum of `total_size` across all entries.
+    pub total_content_size: u64,
+    /// On-disk byte size of the rotate-log segments backing this store.
+    pub on_disk_size: u64,
+    /// The largest single entry's `total_size`.
+    pub largest_entry: u64,
+    /// Histogram of entry `total_size` values, bucketed by `size_bucket`.
+    pub size_histogram: BTreeMap<u32, usize>,
+}
+
+/// Sum the apparent size of every regular file under `path`, recursing into subdirectories.
+/// Used to report the on-disk footprint of the rotate-log segments that back an `AuxStore`.
+fn dir_size(path: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Load the at-rest encryption key from the file referenced by
+/// `indexedlog.aux.encryption-key-path`, if configured. The file must contain exactly 32 raw
+/// key bytes. Returns `None` when the config isn't set, so stores are unencrypted by default.
+fn load_encryption_key(config: &ConfigSet) -> Result<Option<[u8; 32]>> {
+    let path = match config.get_opt::<String>("indexedlog", "aux.encryption-key-path")? {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    let bytes = std::fs::read(&path)
+        .with_context(|| format!("failed to read auxstore encryption key from {}", path))?;
+    if bytes.len() != 32 {
+        bail!(
+            "auxstore encryption key at {} must be exactly 32 bytes, got {}",
+            path,
+            bytes.len()
+        );
+    }
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&bytes);
+    Ok(Some(key))
+}
+
+pub struct AuxStore {
+    inner: RwLock<AuxStoreInner>,
+    path: PathBuf,
+}
 
 impl AuxStore {
     pub fn new(path: impl AsRef<Path>, config: &ConfigSet, store_type: StoreType) -> Result<Self> {
@@ -163,15 +520,48 @@ impl AuxStore {
             StoreType::Shared => open_options.shared(&path),
         }?;
 
-        Ok(AuxStore(RwLock::new(AuxStoreInner(log))))
+        // Only `Local` stores get a Bloom filter: `Shared` stores are read and written by
+        // multiple processes, and this in-memory filter (built once, on open) would otherwise
+        // report false negatives for entries a sibling process appended afterwards. See
+        // `BloomFilter`'s doc comment.
+        let bloom_enabled = matches!(store_type, StoreType::Local)
+            && config
+                .get_opt::<bool>("indexedlog", "aux.bloom-filter-enabled")?
+                .unwrap_or(true);
+
+        let key = load_encryption_key(config)?;
+
+        let inner = if bloom_enabled {
+            let false_positive_rate = config
+                .get_opt::<f64>("indexedlog", "aux.bloom-filter-fpr")?
+                .unwrap_or(0.01);
+            let probe = AuxStoreInner { log, bloom: None, key };
+            let hgids = probe.hgids()?;
+            let mut bloom = BloomFilter::with_capacity(hgids.len(), false_positive_rate);
+            for hgid in &hgids {
+                bloom.set(hgid);
+            }
+            AuxStoreInner {
+                log: probe.log,
+                bloom: Some(bloom),
+                key,
+            }
+        } else {
+            AuxStoreInner { log, bloom: None, key }
+        };
+
+        Ok(AuxStore {
+            inner: RwLock::new(inner),
+            path: path.as_ref().to_path_buf(),
+        })
     }
 
     pub fn read<'a>(&'a self) -> AuxStoreReadGuard<'a> {
-        AuxStoreReadGuard(self.0.read())
+        AuxStoreReadGuard(self.inner.read(), &self.path)
     }
 
     pub fn write<'a>(&'a self) -> AuxStoreWriteGuard<'a> {
-        AuxStoreWriteGuard(self.0.write())
+        AuxStoreWriteGuard(self.inner.write())
     }
 
     fn open_options(config: &ConfigSet) -> Result<StoreOpenOptions> {
@@ -204,16 +594,38 @@ impl AuxStore {
     }
 }
 
-pub struct AuxStoreReadGuard<'a>(RwLockReadGuard<'a, AuxStoreInner>);
+pub struct AuxStoreReadGuard<'a>(RwLockReadGuard<'a, AuxStoreInner>, &'a Path);
 
 impl AuxStoreReadGuard<'_> {
     pub fn get(&self, hgid: HgId) -> Result<Option<Entry>> {
         self.0.get(hgid)
     }
 
+    /// Look up `hgids` in one guard acquisition, returning only the ones found.
+    pub fn get_many(&self, hgids: &[HgId]) -> Result<HashMap<HgId, Entry>> {
+        self.0.get_many(hgids)
+    }
+
+    /// Streaming variant of `get_many` for callers resolving a large manifest who'd rather not
+    /// materialize the whole result map up front.
+    pub fn get_many_stream<'b>(
+        &'b self,
+        hgids: &'b [HgId],
+    ) -> impl Iterator<Item = Result<(HgId, Entry)>> + 'b {
+        self.0.get_many_stream(hgids)
+    }
+
     pub(crate) fn hgids(&self) -> Result<Vec<HgId>> {
         self.0.hgids()
     }
+
+    /// Compute aggregate statistics about this store by walking the log once. `on_disk_size`
+    /// additionally sums the size of the rotate-log segment files under the store's directory.
+    pub fn stats(&self) -> Result<AuxStoreStats> {
+        let mut stats = self.0.stats()?;
+        stats.on_disk_size = dir_size(self.1)?;
+        Ok(stats)
+    }
 }
 
 pub struct AuxStoreWriteGuard<'a>(RwLockWriteGuard<'a, AuxStoreInner>);
@@ -230,6 +642,11 @@ impl AuxStoreWriteGuard<'_> {
         self.0.get(hgid)
     }
 
+    /// Look up `hgids` in one guard acquisition, returning only the ones found.
+    pub fn get_many(&self, hgids: &[HgId]) -> Result<HashMap<HgId, Entry>> {
+        self.0.get_many(hgids)
+    }
+
     pub fn put(&mut self, hgid: HgId, entry: &Entry) -> Result<()> {
         self.0.put(hgid, entry)
     }
@@ -280,6 +697,140 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_get_many() -> Result<()> {
+        let tempdir = TempDir::new()?;
+        let store = AuxStore::new(&tempdir, &ConfigSet::new(), StoreType::Shared)?;
+
+        let mut entry_a = Entry::default();
+        entry_a.total_size = 1;
+        let mut entry_b = Entry::default();
+        entry_b.total_size = 2;
+
+        let k1 = key("a", "1");
+        let k2 = key("a", "2");
+        let k3 = key("a", "3");
+
+        store.write().put(k1.hgid, &entry_a)?;
+        store.write().put(k2.hgid, &entry_b)?;
+        store.write().flush()?;
+
+        let found = store.read().get_many(&[k1.hgid, k2.hgid, k3.hgid])?;
+        assert_eq!(found.len(), 2);
+        assert_eq!(found.get(&k1.hgid), Some(&entry_a));
+        assert_eq!(found.get(&k2.hgid), Some(&entry_b));
+        assert_eq!(found.get(&k3.hgid), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats() -> Result<()> {
+        let tempdir = TempDir::new()?;
+        let store = AuxStore::new(&tempdir, &ConfigSet::new(), StoreType::Shared)?;
+
+        let mut entry_a = Entry::default();
+        entry_a.total_size = 1;
+        let mut entry_b = Entry::default();
+        entry_b.total_size = 3;
+
+        let k1 = key("a", "1");
+        let k2 = key("a", "2");
+
+        store.write().put(k1.hgid, &entry_a)?;
+        store.write().put(k2.hgid, &entry_b)?;
+        store.write().put(k1.hgid, &entry_a)?;
+        store.write().flush()?;
+
+        let stats = store.read().stats()?;
+        assert_eq!(stats.live_entries, 3);
+        assert_eq!(stats.distinct_hgids, 2);
+        assert_eq!(stats.duplicate_hgids, 1);
+        assert_eq!(stats.total_content_size, 5);
+        assert_eq!(stats.largest_entry, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_deserialize_roundtrip_with_optional_digests() -> Result<()> {
+        let mut entry = Entry::default();
+        entry.total_size = 7;
+        entry.content_crc32 = Some(0xdead_beef);
+        entry.content_blake3 = Some([9u8; 32]);
+
+        let hgid = key("a", "1").hgid;
+        let bytes = entry.serialize(hgid, None)?;
+        let (found_hgid, found_entry) = Entry::deserialize(bytes, None)?;
+
+        assert_eq!(hgid, found_hgid);
+        assert_eq!(entry, found_entry);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_version_0_has_no_optional_digests() -> Result<()> {
+        let mut entry = Entry::default();
+        entry.total_size = 7;
+
+        let hgid = key("a", "1").hgid;
+        let bytes = entry.serialize(hgid, None)?;
+        let (_, found_entry) = Entry::deserialize(bytes, None)?;
+
+        assert_eq!(found_entry.content_crc32(), None);
+        assert_eq!(found_entry.content_blake3(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_entry_roundtrip() -> Result<()> {
+        let enc_key = [7u8; 32];
+        let hgid = key("a", "1").hgid;
+
+        let mut entry = Entry::default();
+        entry.total_size = 42;
+        entry.content_blake3 = Some([3u8; 32]);
+
+        let bytes = entry.serialize(hgid, Some(&enc_key))?;
+        let (found_hgid, found_entry) = Entry::deserialize(bytes, Some(&enc_key))?;
+
+        assert_eq!(hgid, found_hgid);
+        assert_eq!(entry, found_entry);
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_entry_without_key_fails() -> Result<()> {
+        let hgid = key("a", "1").hgid;
+        let bytes = Entry::default().serialize(hgid, Some(&[7u8; 32]))?;
+
+        assert!(Entry::deserialize(bytes, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_entry_with_wrong_key_fails() -> Result<()> {
+        let hgid = key("a", "1").hgid;
+        let bytes = Entry::default().serialize(hgid, Some(&[7u8; 32]))?;
+
+        assert!(Entry::deserialize(bytes, Some(&[9u8; 32])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bloom_filter_no_false_negatives() {
+        let keys: Vec<HgId> = (0..200u32)
+            .map(|i| key("a", &i.to_string()).hgid)
+            .collect();
+
+        let mut bloom = BloomFilter::with_capacity(keys.len(), 0.01);
+        for hgid in &keys {
+            bloom.set(hgid);
+        }
+
+        for hgid in &keys {
+            assert!(bloom.might_contain(hgid));
+        }
+    }
+
     #[test]
     fn test_lookup_failure() -> Result<()> {
         let tempdir = TempDir::new().unwrap();